@@ -2,14 +2,45 @@ use structopt::StructOpt;
 use std::fs;
 use std::io::{self, Write};
 use std::error::Error;
+use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use std::sync::Arc;
+use axum::{Router, Json};
+use axum::routing::{get, post};
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
 use reqwest::{Client, Url, Response, Method, header};
 use serde::{Serialize, Deserialize};
+use secrecy::{ExposeSecret, SecretString};
+use arboard::Clipboard;
+use argon2::{Algorithm, Argon2, Params, Version};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "card")]
+struct Opt {
+    /// Profile to use from the config file
+    #[structopt(long, global = true)]
+    profile: Option<String>,
+
+    /// Filter the default listing by card state
+    #[structopt(long, possible_values = &["OPEN", "PAUSED", "CLOSED", "ALL"])]
+    state: Option<String>,
+
+    /// Cap the number of cards listed (bounds API round-trips)
+    #[structopt(long)]
+    limit: Option<usize>,
+
+    #[structopt(subcommand)]
+    command: Option<CommandCard>,
+}
+
+#[derive(StructOpt, Debug)]
 enum CommandCard {
-    #[structopt(name = "default")]
-    Default,
     /// Authenticate with privacy.com
     Auth,
     /// Create a new card
@@ -19,7 +50,79 @@ enum CommandCard {
 
         /// Amount limit for the card
         amount: u32,
-    }
+    },
+    /// Reveal a card's PAN by copying it to the clipboard
+    Show {
+        /// Card token or last four digits
+        token: String,
+
+        /// Wipe the clipboard again after this many seconds
+        #[structopt(long)]
+        clear_after: Option<u64>,
+    },
+    /// Pause a card so it declines new charges
+    Pause {
+        /// Card token or last four digits
+        token: String,
+    },
+    /// Close a card permanently
+    Close {
+        /// Card token or last four digits
+        token: String,
+    },
+    /// Update a card's spend limit
+    Update {
+        /// Card token or last four digits
+        token: String,
+
+        /// New spend limit, in cents
+        #[structopt(long)]
+        limit: u32,
+
+        /// Window the spend limit applies over
+        #[structopt(long, possible_values = &["MONTHLY", "ANNUALLY", "TRANSACTION", "FOREVER"])]
+        duration: Option<String>,
+    },
+    /// Run a local HTTP daemon exposing the card operations
+    Serve {
+        /// Port to listen on
+        #[structopt(long, default_value = "8080")]
+        port: u16,
+
+        /// Address to bind to
+        #[structopt(long, default_value = "127.0.0.1")]
+        bind: String,
+    },
+    /// List recent transactions
+    Transactions {
+        /// Only show transactions for this card token
+        #[structopt(long)]
+        card: Option<String>,
+
+        /// Page of results to fetch
+        #[structopt(long)]
+        page: Option<u32>,
+
+        /// Continuously poll and print newly-seen transactions
+        #[structopt(long)]
+        follow: bool,
+    },
+    /// Manage configuration profiles
+    Config {
+        #[structopt(subcommand)]
+        cmd: ConfigCommand,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+enum ConfigCommand {
+    /// List the configured profiles
+    List,
+    /// Set the default profile
+    SetDefault {
+        /// Profile name to make the default
+        name: String,
+    },
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -43,8 +146,8 @@ struct Card {
     state: String,
     funding: Funding,
     auth_rule_tokens: Vec<String>,
-    pan: Option<String>,
-    cvv: Option<String>,
+    pan: Option<SecretString>,
+    cvv: Option<SecretString>,
     exp_month: String,
     exp_year: String,
 }
@@ -61,6 +164,24 @@ struct Funding {
     last_four: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+struct Transactions {
+    data: Vec<Transaction>,
+    total_pages: i32,
+    page: i32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Transaction {
+    token: String,
+    created: String,
+    /// Settled/authorized amount, in cents.
+    amount: i64,
+    status: String,
+    descriptor: String,
+    card_token: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct CardCreationPayload {
     #[serde(rename = "type")]
@@ -71,6 +192,16 @@ struct CardCreationPayload {
     state: String,
 }
 
+#[derive(Debug, Default, Serialize)]
+struct CardUpdatePayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spend_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spend_limit_duration: Option<String>,
+}
+
 const BASE_URL: &str = "https://api.privacy.com/v1/";
 
 struct ApiClient {
@@ -107,11 +238,7 @@ impl ApiClient {
         }
 
         let response = request.send().await?;
-        let text = response.text().await.unwrap();
-        println!("{text:?}");
-
-        todo!()
-        // Ok(response)
+        Ok(response)
     }
 
     async fn get(&self, endpoint: &str) -> Result<Response, Box<dyn Error>> {
@@ -121,12 +248,54 @@ impl ApiClient {
     async fn post<T: Serialize>(&self, endpoint: &str, body: T) -> Result<Response, Box<dyn Error>> {
         self.request(Method::POST, endpoint, Some(body)).await
     }
-    async fn list(&self) -> Result<Cards, Box<dyn Error>> {
-        let response = self.get("cards").await?;
-        // println!("{}", response.text().await.unwrap());
-        let card: Cards = response.json().await?;
-        // todo!()
-        Ok(card)
+
+    async fn patch<T: Serialize>(&self, endpoint: &str, body: T) -> Result<Response, Box<dyn Error>> {
+        self.request(Method::PATCH, endpoint, Some(body)).await
+    }
+    /// Walk every page of the `cards` endpoint, accumulating all entries.
+    ///
+    /// `state` is pushed down to the API as a filter (`OPEN`/`PAUSED`/`CLOSED`,
+    /// or `None`/`ALL` for every state). The same filter is re-applied
+    /// client-side as a guard, so the default listing stays correct even if the
+    /// endpoint ignores the `state` query param. `limit` caps the number of
+    /// cards returned, which also bounds how many paged round-trips are made.
+    async fn list(&self, state: Option<&str>, limit: Option<usize>) -> Result<Vec<Card>, Box<dyn Error>> {
+        let filter = state.filter(|state| *state != "ALL");
+        let mut all: Vec<Card> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let mut endpoint = format!("cards?page={page}");
+            if let Some(filter) = filter {
+                endpoint.push_str(&format!("&state={filter}"));
+            }
+
+            let response = self.get(&endpoint).await?;
+            let cards: Cards = response.json().await?;
+            all.extend(
+                cards
+                    .data
+                    .into_iter()
+                    .filter(|card| match filter {
+                        Some(filter) => card.state == filter,
+                        None => true,
+                    }),
+            );
+
+            if let Some(limit) = limit {
+                if all.len() >= limit {
+                    all.truncate(limit);
+                    break;
+                }
+            }
+
+            if cards.page >= cards.total_pages {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(all)
     }
 
     async fn create_card(&self, payload: CardCreationPayload) -> Result<Card, Box<dyn Error>> {
@@ -134,6 +303,260 @@ impl ApiClient {
         let card: Card = response.json().await?;
         Ok(card)
     }
+
+    async fn update_card(&self, token: &str, payload: CardUpdatePayload) -> Result<Card, Box<dyn Error>> {
+        let endpoint = format!("cards/{token}");
+        let response = self.patch(&endpoint, payload).await?;
+        let card: Card = response.json().await?;
+        Ok(card)
+    }
+
+    async fn get_card(&self, token: &str) -> Result<Card, Box<dyn Error>> {
+        let endpoint = format!("cards/{token}");
+        let response = self.get(&endpoint).await?;
+        let card: Card = response.json().await?;
+        Ok(card)
+    }
+
+    async fn transactions(
+        &self,
+        card_token: Option<&str>,
+        page: Option<u32>,
+    ) -> Result<Transactions, Box<dyn Error>> {
+        let mut params: Vec<String> = Vec::new();
+        if let Some(card_token) = card_token {
+            params.push(format!("card_token={card_token}"));
+        }
+        if let Some(page) = page {
+            params.push(format!("page={page}"));
+        }
+        let mut endpoint = String::from("transactions");
+        if !params.is_empty() {
+            endpoint.push('?');
+            endpoint.push_str(&params.join("&"));
+        }
+        let response = self.get(&endpoint).await?;
+        let transactions: Transactions = response.json().await?;
+        Ok(transactions)
+    }
+}
+
+/// User configuration, deserialized from `$XDG_CONFIG_HOME/card/config.toml`.
+///
+/// A profile bundles the key file and the per-profile card-creation defaults
+/// for one privacy.com account, so users with several accounts can switch
+/// between them with `--profile`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Config {
+    /// Profile used when `--profile` is not given.
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Profile {
+    /// Path to this profile's encrypted key file.
+    key_file: Option<String>,
+    /// Default `spend_limit_duration` for newly created cards.
+    spend_limit_duration: Option<String>,
+    /// Default card `type` for newly created cards.
+    card_type: Option<String>,
+}
+
+impl Config {
+    /// Read the config from disk, returning the default (empty) config when the
+    /// file does not exist yet.
+    fn load() -> Result<Config, Box<dyn Error>> {
+        match get_xdg_config_path() {
+            Some(path) if path.exists() => {
+                let text = fs::read_to_string(&path)?;
+                Ok(toml::from_str(&text)?)
+            }
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// Serialize the config to disk, creating the directory on first write.
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = get_xdg_config_path().ok_or("Cannot determine config directory.")?;
+        if let Some(parent_dir) = path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A profile with all defaults resolved, ready to drive a command.
+struct ResolvedProfile {
+    key_path: PathBuf,
+    spend_limit_duration: String,
+    card_type: String,
+}
+
+/// Resolve the selected profile's key path and card-creation defaults, falling
+/// back to the legacy single-key location and the built-in defaults.
+fn resolve_profile(selected: Option<&str>) -> Result<ResolvedProfile, Box<dyn Error>> {
+    let config = Config::load()?;
+    let name = selected
+        .map(str::to_owned)
+        .or_else(|| config.default_profile.clone());
+
+    let profile = match name.as_deref() {
+        Some(name) => Some(
+            config
+                .profiles
+                .get(name)
+                .ok_or_else(|| format!("No such profile: {name}"))?,
+        ),
+        // No profile requested at all: fall back to the legacy single-key setup.
+        None => None,
+    };
+
+    // A named profile must carry its own `key_file`; otherwise two profiles
+    // without one would silently share the single legacy key, defeating the
+    // multi-account purpose. The legacy path is only used when no profile was
+    // requested at all.
+    let key_path = match profile {
+        Some(profile) => PathBuf::from(profile.key_file.as_ref().ok_or_else(|| {
+            format!(
+                "Profile '{}' has no key_file set; add `key_file = \"...\"` to its entry in config.toml",
+                name.as_deref().unwrap_or_default()
+            )
+        })?),
+        None => get_xdg_data_home().ok_or("Cannot determine key file location.")?,
+    };
+
+    Ok(ResolvedProfile {
+        key_path,
+        spend_limit_duration: profile
+            .and_then(|p| p.spend_limit_duration.clone())
+            .unwrap_or_else(|| "TRANSACTION".to_owned()),
+        card_type: profile
+            .and_then(|p| p.card_type.clone())
+            .unwrap_or_else(|| "SINGLE_USE".to_owned()),
+    })
+}
+
+/// Location of the TOML config file, mirroring [`get_xdg_data_home`].
+fn get_xdg_config_path() -> Option<std::path::PathBuf> {
+    std::env::var("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|_| {
+            std::env::var("HOME").map(|home| {
+                let mut path = std::path::PathBuf::from(home);
+                path.push(".config");
+                path
+            })
+        })
+        .map(|mut path| {
+            path.push("card/config.toml");
+            path
+        })
+        .ok()
+}
+
+/// Magic prefix identifying an encrypted key file. Legacy plaintext files lack
+/// it, which is how [`KeyStore::load`] tells the two formats apart.
+const KEY_MAGIC: &[u8; 5] = b"CARD1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// At-rest storage for the privacy.com secret key.
+///
+/// The secret is sealed with AES-256-GCM under a 32-byte key derived from the
+/// user's unlock passphrase via Argon2id, so a copy of the file is useless
+/// without the passphrase. The on-disk frame is
+/// `KEY_MAGIC || salt[16] || nonce[12] || ciphertext`.
+struct KeyStore {
+    path: PathBuf,
+}
+
+impl KeyStore {
+    fn new(path: PathBuf) -> Self {
+        KeyStore { path }
+    }
+
+    /// Derive the 32-byte symmetric key from `passphrase` and `salt`.
+    fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], Box<dyn Error>> {
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default());
+        let mut key = [0u8; 32];
+        argon2
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("key derivation failed: {e}"))?;
+        Ok(key)
+    }
+
+    /// Encrypt `secret` under `passphrase` and write the framed file, creating
+    /// the parent directory if needed.
+    fn save(&self, secret: &str, passphrase: &str) -> Result<(), Box<dyn Error>> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = Self::derive_key(passphrase, &salt)?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), secret.as_bytes())
+            .map_err(|_| "failed to encrypt secret key")?;
+
+        let mut framed = Vec::with_capacity(KEY_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        framed.extend_from_slice(KEY_MAGIC);
+        framed.extend_from_slice(&salt);
+        framed.extend_from_slice(&nonce);
+        framed.extend_from_slice(&ciphertext);
+
+        if let Some(parent_dir) = self.path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+        fs::write(&self.path, framed)?;
+        Ok(())
+    }
+
+    /// Whether the stored file predates encryption (no [`KEY_MAGIC`] prefix).
+    fn is_legacy(&self) -> bool {
+        match fs::read(&self.path) {
+            Ok(bytes) => !bytes.starts_with(KEY_MAGIC),
+            Err(_) => false,
+        }
+    }
+
+    /// Decrypt and return the secret key. Legacy plaintext files are returned
+    /// with surrounding whitespace trimmed (and re-encrypted on the next
+    /// `card auth`).
+    fn load(&self, passphrase: &str) -> Result<String, Box<dyn Error>> {
+        let bytes = fs::read(&self.path)?;
+
+        if !bytes.starts_with(KEY_MAGIC) {
+            // Legacy plaintext key, written before at-rest encryption existed.
+            return Ok(String::from_utf8(bytes)?.trim().to_owned());
+        }
+
+        let rest = &bytes[KEY_MAGIC.len()..];
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            return Err("key file is corrupt".into());
+        }
+        let salt = &rest[..SALT_LEN];
+        let nonce = &rest[SALT_LEN..SALT_LEN + NONCE_LEN];
+        let ciphertext = &rest[SALT_LEN + NONCE_LEN..];
+
+        let key = Self::derive_key(passphrase, salt)?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "wrong passphrase")?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+/// Prompt for a secret (the API key or the unlock passphrase) on the terminal,
+/// without echoing it.
+fn prompt_passphrase(prompt: &str) -> String {
+    print!("{prompt}");
+    io::stdout().flush().unwrap();
+    rpassword::read_password().unwrap()
 }
 
 fn get_xdg_data_home() -> Option<std::path::PathBuf> {
@@ -149,35 +572,23 @@ fn get_xdg_data_home() -> Option<std::path::PathBuf> {
         .ok()
 }
 
-async fn handle_default_command() {
-    if let Some(xdg_data_path) = get_xdg_data_home() {
-        if xdg_data_path.exists() {
-            match fs::read_to_string(&xdg_data_path) {
-                Ok(content) => {
-                    let client = ApiClient::new(&content);
-                    let cards = client.list().await.unwrap();
-                    let open: Vec<Card> = cards.data
-                        .iter()
-                        .filter_map(|card| if card.state == "OPEN" { Some(card.to_owned()) } else { None })
-                        .collect();
-                    for card in open {
-                        println!("{}", card.memo);
-                    }
-
-                },
-                Err(e) => {
-                    eprintln!("Error reading key file: {}", e);
-                }
+async fn handle_default_command(profile: Option<&str>, state: Option<String>, limit: Option<usize>) {
+    let client = match client_for(profile) {
+        Some(client) => client,
+        None => return,
+    };
+    let filter = state.as_deref().unwrap_or("OPEN");
+    match client.list(Some(filter), limit).await {
+        Ok(cards) => {
+            for card in &cards {
+                println!("{}", card.memo);
             }
-        } else {
-            println!("Please run 'card auth' to login to privacy.com");
         }
-    } else {
-        println!("Cannot determine XDG data directory.");
+        Err(e) => eprintln!("Failed to list cards: {}", e),
     }
 }
 
-fn handle_auth_command() {
+fn handle_auth_command(profile: Option<&str>) {
     let auth_url = "https://app.privacy.com/account";
 
     println!("Opening '{auth_url}' in your browser...");
@@ -187,73 +598,524 @@ fn handle_auth_command() {
         return;
     }
 
-    print!("Enter your secret key: ");
-    io::stdout().flush().unwrap(); // flush the prompt to ensure it appears before the hidden input
-
-    let secret_key = rpassword::read_password().unwrap();
+    let secret_key = prompt_passphrase("Enter your secret key: ");
+    let passphrase = prompt_passphrase("Choose an unlock passphrase: ");
 
+    let resolved = match resolve_profile(profile) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    let store = KeyStore::new(resolved.key_path);
+    match store.save(&secret_key, &passphrase) {
+        Ok(_) => println!("Key saved successfully."),
+        Err(e) => eprintln!("Failed to save key: {}", e),
+    }
+}
 
-    if let Some(xdg_data_path) = get_xdg_data_home() {
-        if let Some(parent_dir) = xdg_data_path.parent() {
-            if let Err(e) = fs::create_dir_all(parent_dir) {
-                eprintln!("Failed to create directory: {}", e);
-                return;
+async fn handle_create_command(name: String, amount: u32, profile: Option<&str>) {
+    let resolved = match resolve_profile(profile) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    let xdg_data_path = resolved.key_path;
+    if xdg_data_path.exists() {
+        let store = KeyStore::new(xdg_data_path);
+        let passphrase = if store.is_legacy() {
+            String::new()
+        } else {
+            prompt_passphrase("Enter your unlock passphrase: ")
+        };
+        match store.load(&passphrase) {
+            Ok(content) => {
+                let client = ApiClient::new(&content);
+                match client.create_card(CardCreationPayload {
+                    card_type: resolved.card_type,
+                    memo: name,
+                    spend_limit: amount,
+                    spend_limit_duration: resolved.spend_limit_duration,
+                    state: "OPEN".to_owned(),
+                }).await {
+                    Ok(_) => println!("Card created"),
+                    Err(e) => eprintln!("Failed to create card: {}", e),
+                }
+            },
+            Err(e) => {
+                eprintln!("Error reading key file: {}", e);
             }
         }
+    } else {
+        println!("Please run 'card auth' to login to privacy.com");
+    }
+}
 
-        match fs::write(&xdg_data_path, &secret_key) {
-            Ok(_) => println!("Key saved successfully."),
-            Err(e) => eprintln!("Failed to save key: {}", e),
+/// Resolve the profile, unlock its key file, and build an authenticated client.
+/// Prints the appropriate guidance and returns `None` on any failure.
+fn client_for(profile: Option<&str>) -> Option<ApiClient> {
+    let resolved = match resolve_profile(profile) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{e}");
+            return None;
         }
+    };
+    if !resolved.key_path.exists() {
+        println!("Please run 'card auth' to login to privacy.com");
+        return None;
+    }
+    let store = KeyStore::new(resolved.key_path);
+    let passphrase = if store.is_legacy() {
+        String::new()
     } else {
-        println!("Cannot determine XDG data directory.");
-    }
-}
-
-async fn handle_create_command(name: String, amount: u32) {
-    if let Some(xdg_data_path) = get_xdg_data_home() {
-        if xdg_data_path.exists() {
-            match fs::read_to_string(&xdg_data_path) {
-                Ok(content) => {
-                    let client = ApiClient::new(&content);
-                    client.create_card(CardCreationPayload {
-                        card_type: "SINGLE_USE".to_owned(),
-                        memo: name,
-                        spend_limit: amount,
-                        spend_limit_duration: "TRANSACTION".to_owned(),
-                        state: "OPEN".to_owned(),
-                    }).await.unwrap();
-                    println!("Card created");
-
-                },
-                Err(e) => {
-                    eprintln!("Error reading key file: {}", e);
+        prompt_passphrase("Enter your unlock passphrase: ")
+    };
+    match store.load(&passphrase) {
+        Ok(content) => Some(ApiClient::new(&content)),
+        Err(e) => {
+            eprintln!("Error reading key file: {}", e);
+            None
+        }
+    }
+}
+
+/// Resolve a user-supplied card reference to a full token. A four-digit
+/// reference is treated as a `last_four` and looked up via the card list;
+/// anything else is assumed to already be a token.
+async fn resolve_card_token(client: &ApiClient, reference: &str) -> Result<String, Box<dyn Error>> {
+    if reference.len() == 4 && reference.chars().all(|c| c.is_ascii_digit()) {
+        let cards = client.list(None, None).await?;
+        let matches: Vec<&Card> = cards
+            .iter()
+            .filter(|card| card.last_four == reference)
+            .collect();
+        match matches.as_slice() {
+            [] => Err(format!("No card found with last four '{reference}'").into()),
+            [card] => Ok(card.token.clone()),
+            _ => Err(format!("Multiple cards match last four '{reference}'; use the full token").into()),
+        }
+    } else {
+        Ok(reference.to_owned())
+    }
+}
+
+async fn set_card_state(reference: String, state: &str, profile: Option<&str>) {
+    let client = match client_for(profile) {
+        Some(client) => client,
+        None => return,
+    };
+    let token = match resolve_card_token(&client, &reference).await {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    let payload = CardUpdatePayload {
+        state: Some(state.to_owned()),
+        ..Default::default()
+    };
+    match client.update_card(&token, payload).await {
+        Ok(card) => println!("Card {} is now {}", card.last_four, card.state),
+        Err(e) => eprintln!("Failed to update card: {}", e),
+    }
+}
+
+async fn handle_show_command(reference: String, clear_after: Option<u64>, profile: Option<&str>) {
+    let client = match client_for(profile) {
+        Some(client) => client,
+        None => return,
+    };
+    let token = match resolve_card_token(&client, &reference).await {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    let card = match client.get_card(&token).await {
+        Ok(card) => card,
+        Err(e) => {
+            eprintln!("Failed to fetch card: {}", e);
+            return;
+        }
+    };
+
+    let pan = match &card.pan {
+        Some(pan) => pan,
+        None => {
+            eprintln!("Card PAN is not available for this card.");
+            return;
+        }
+    };
+
+    // Hold onto the clipboard handle: on Linux/X11 the selection is served by
+    // the process that owns it, so it vanishes the moment we exit. Keeping this
+    // instance alive (until the timer fires or the user presses Enter) is what
+    // makes the copied value actually pasteable.
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            eprintln!("Failed to access clipboard: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = clipboard.set_text(pan.expose_secret().to_owned()) {
+        eprintln!("Failed to copy to clipboard: {}", e);
+        return;
+    }
+    println!("PAN copied to clipboard.");
+
+    // Only the masked tail ever reaches the terminal history.
+    println!(
+        "Card ending {} · exp {}/{} · {}",
+        card.last_four, card.exp_month, card.exp_year, card.memo
+    );
+
+    // The CVV stays an in-memory secret; we only note its presence rather than
+    // printing it or echoing it through the clipboard.
+    if card.cvv.is_some() {
+        println!("(CVV available but not printed)");
+    }
+
+    match clear_after {
+        Some(seconds) => {
+            println!("Clipboard will be cleared in {seconds}s...");
+            tokio::time::sleep(Duration::from_secs(seconds)).await;
+            let _ = clipboard.set_text(String::new());
+            println!("Clipboard cleared.");
+        }
+        None => {
+            println!("Holding clipboard; press Enter to clear and exit...");
+            let mut line = String::new();
+            let _ = io::stdin().read_line(&mut line);
+            let _ = clipboard.set_text(String::new());
+            println!("Clipboard cleared.");
+        }
+    }
+}
+
+async fn handle_update_command(
+    reference: String,
+    limit: u32,
+    duration: Option<String>,
+    profile: Option<&str>,
+) {
+    let client = match client_for(profile) {
+        Some(client) => client,
+        None => return,
+    };
+    let token = match resolve_card_token(&client, &reference).await {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    let payload = CardUpdatePayload {
+        spend_limit: Some(limit),
+        spend_limit_duration: duration,
+        ..Default::default()
+    };
+    match client.update_card(&token, payload).await {
+        Ok(card) => println!(
+            "Card {} limit updated to {} ({})",
+            card.last_four, card.spend_limit, card.spend_limit_duration
+        ),
+        Err(e) => eprintln!("Failed to update card: {}", e),
+    }
+}
+
+fn print_transaction(tx: &Transaction) {
+    println!(
+        "{:<26} {:>9.2}  {:<10} {:<24} {}",
+        tx.created,
+        tx.amount as f64 / 100.0,
+        tx.status,
+        tx.descriptor,
+        tx.card_token.as_deref().unwrap_or("-"),
+    );
+}
+
+async fn handle_transactions_command(
+    card: Option<String>,
+    page: Option<u32>,
+    follow: bool,
+    profile: Option<&str>,
+) {
+    let client = match client_for(profile) {
+        Some(client) => client,
+        None => return,
+    };
+    let card = card.as_deref();
+
+    let transactions = match client.transactions(card, page).await {
+        Ok(transactions) => transactions,
+        Err(e) => {
+            eprintln!("Failed to fetch transactions: {}", e);
+            return;
+        }
+    };
+
+    println!(
+        "{:<26} {:>9}  {:<10} {:<24} CARD",
+        "CREATED", "AMOUNT", "STATUS", "DESCRIPTOR",
+    );
+
+    let mut seen: HashSet<String> = HashSet::new();
+    for tx in &transactions.data {
+        print_transaction(tx);
+        seen.insert(tx.token.clone());
+    }
+
+    if !follow {
+        return;
+    }
+
+    // Re-poll the feed and print only transactions we have not seen before,
+    // so a user can watch a single-use card get charged in real time.
+    loop {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        match client.transactions(card, page).await {
+            Ok(transactions) => {
+                for tx in transactions.data.iter().rev() {
+                    if seen.insert(tx.token.clone()) {
+                        print_transaction(tx);
+                    }
                 }
             }
-        } else {
-            println!("Please run 'card auth' to login to privacy.com");
+            Err(e) => eprintln!("Failed to poll transactions: {}", e),
         }
+    }
+}
+
+fn handle_config_command(cmd: ConfigCommand) {
+    let mut config = match Config::load() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to read config: {e}");
+            return;
+        }
+    };
+
+    match cmd {
+        ConfigCommand::List => {
+            if config.profiles.is_empty() {
+                println!("No profiles configured.");
+                return;
+            }
+            let default = config.default_profile.as_deref();
+            for name in config.profiles.keys() {
+                let marker = if Some(name.as_str()) == default { " (default)" } else { "" };
+                println!("{name}{marker}");
+            }
+        }
+        ConfigCommand::SetDefault { name } => {
+            if !config.profiles.contains_key(&name) {
+                eprintln!("No such profile: {name}");
+                return;
+            }
+            config.default_profile = Some(name.clone());
+            match config.save() {
+                Ok(_) => println!("Default profile set to '{name}'."),
+                Err(e) => eprintln!("Failed to save config: {e}"),
+            }
+        }
+    }
+}
+
+/// Shared state handed to every request handler of the `serve` daemon. The
+/// privacy.com client (and thus the secret key) stays in process memory; only
+/// the locally-generated bearer token is shared with clients.
+#[derive(Clone)]
+struct ServerState {
+    client: Arc<ApiClient>,
+    bearer: Arc<String>,
+    spend_limit_duration: String,
+    card_type: String,
+}
+
+/// Client-facing view of a card. PAN is only populated on creation, never on
+/// listings, and the API secret key is never exposed.
+#[derive(Serialize)]
+struct CardResponse {
+    token: String,
+    last_four: String,
+    memo: String,
+    state: String,
+    spend_limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pan: Option<String>,
+}
+
+impl CardResponse {
+    fn summary(card: &Card) -> Self {
+        CardResponse {
+            token: card.token.clone(),
+            last_four: card.last_four.clone(),
+            memo: card.memo.clone(),
+            state: card.state.clone(),
+            spend_limit: card.spend_limit,
+            pan: None,
+        }
+    }
+
+    fn with_pan(card: &Card) -> Self {
+        let mut response = Self::summary(card);
+        response.pan = card.pan.as_ref().map(|pan| pan.expose_secret().to_owned());
+        response
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateCardRequest {
+    name: String,
+    amount: u32,
+}
+
+/// Reject requests whose `Authorization` header does not carry the daemon's
+/// bearer token.
+fn check_auth(headers: &HeaderMap, state: &ServerState) -> Result<(), StatusCode> {
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    if presented == format!("Bearer {}", state.bearer) {
+        Ok(())
     } else {
-        println!("Cannot determine XDG data directory.");
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+async fn serve_list_cards(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<CardResponse>>, StatusCode> {
+    check_auth(&headers, &state)?;
+    let cards = state
+        .client
+        .list(Some("OPEN"), None)
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    let open = cards.iter().map(CardResponse::summary).collect();
+    Ok(Json(open))
+}
+
+async fn serve_create_card(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    Json(body): Json<CreateCardRequest>,
+) -> Result<Json<CardResponse>, StatusCode> {
+    check_auth(&headers, &state)?;
+    let card = state
+        .client
+        .create_card(CardCreationPayload {
+            card_type: state.card_type.clone(),
+            memo: body.name,
+            spend_limit: body.amount,
+            spend_limit_duration: state.spend_limit_duration.clone(),
+            state: "OPEN".to_owned(),
+        })
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    Ok(Json(CardResponse::with_pan(&card)))
+}
+
+async fn serve_close_card(
+    State(state): State<ServerState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<CardResponse>, StatusCode> {
+    check_auth(&headers, &state)?;
+    let payload = CardUpdatePayload {
+        state: Some("CLOSED".to_owned()),
+        ..Default::default()
+    };
+    let card = state
+        .client
+        .update_card(&token, payload)
+        .await
+        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    Ok(Json(CardResponse::summary(&card)))
+}
+
+async fn handle_serve_command(port: u16, bind: String, profile: Option<&str>) {
+    let resolved = match resolve_profile(profile) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    let client = match client_for(profile) {
+        Some(client) => client,
+        None => return,
+    };
+
+    // A fresh random bearer token, printed once at startup.
+    let mut raw = [0u8; 32];
+    OsRng.fill_bytes(&mut raw);
+    let bearer: String = raw.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    let state = ServerState {
+        client: Arc::new(client),
+        bearer: Arc::new(bearer.clone()),
+        spend_limit_duration: resolved.spend_limit_duration,
+        card_type: resolved.card_type,
+    };
+
+    let app = Router::new()
+        .route("/cards", get(serve_list_cards).post(serve_create_card))
+        .route("/cards/:token/close", post(serve_close_card))
+        .with_state(state);
+
+    let addr = format!("{bind}:{port}");
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to bind {addr}: {e}");
+            return;
+        }
+    };
+
+    println!("card daemon listening on http://{addr}");
+    println!("Authorization: Bearer {bearer}");
+
+    if let Err(e) = axum::serve(listener, app).await {
+        eprintln!("Server error: {e}");
     }
 }
 
 #[tokio::main]
 async fn main() {
-    if std::env::args().len() <= 1 {
-        handle_default_command().await;
-    } else {
-        let opt = CommandCard::from_args();
+    let opt = Opt::from_args();
+    let profile = opt.profile.as_deref();
 
-        match opt {
-            CommandCard::Default => unreachable!(),
-            CommandCard::Auth => {
-                handle_auth_command();
-            },
-            CommandCard::Create { name, amount } => {
-                handle_create_command(name, amount).await;
-            }
+    match opt.command {
+        None => handle_default_command(profile, opt.state, opt.limit).await,
+        Some(CommandCard::Auth) => handle_auth_command(profile),
+        Some(CommandCard::Create { name, amount }) => {
+            handle_create_command(name, amount, profile).await;
+        }
+        Some(CommandCard::Show { token, clear_after }) => {
+            handle_show_command(token, clear_after, profile).await;
+        }
+        Some(CommandCard::Pause { token }) => set_card_state(token, "PAUSED", profile).await,
+        Some(CommandCard::Close { token }) => set_card_state(token, "CLOSED", profile).await,
+        Some(CommandCard::Update { token, limit, duration }) => {
+            handle_update_command(token, limit, duration, profile).await;
+        }
+        Some(CommandCard::Serve { port, bind }) => {
+            handle_serve_command(port, bind, profile).await;
+        }
+        Some(CommandCard::Transactions { card, page, follow }) => {
+            handle_transactions_command(card, page, follow, profile).await;
         }
+        Some(CommandCard::Config { cmd }) => handle_config_command(cmd),
     }
 }
 